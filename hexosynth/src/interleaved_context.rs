@@ -0,0 +1,137 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoSynth. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use hexodsp::nodes::NodeAudioContext;
+
+/// Audio context that lets the [hexodsp::NodeExecutor] write directly into
+/// an interleaved `L,R,L,R...` output buffer, as handed out by CPAL and
+/// many other embedding hosts. This saves the transpose step from
+/// per channel buffers.
+///
+/// `output` must hold at least `nframes * channels` samples. Output
+/// channels beyond `channels` are silently dropped, channels that are
+/// never written keep whatever the buffer contained before, so clear it
+/// before calling [hexodsp::NodeExecutor::process].
+pub struct InterleavedContext<'a, 'b, 'c> {
+    pub nframes: usize,
+    pub channels: usize,
+    pub output: &'a mut [f32],
+    pub input: &'b [&'c [f32]],
+}
+
+impl<'a, 'b, 'c> NodeAudioContext for InterleavedContext<'a, 'b, 'c> {
+    #[inline]
+    fn nframes(&self) -> usize {
+        self.nframes
+    }
+
+    #[inline]
+    fn output(&mut self, channel: usize, frame: usize, v: f32) {
+        if channel < self.channels {
+            self.output[frame * self.channels + channel] = v;
+        }
+    }
+
+    #[inline]
+    fn input(&mut self, channel: usize, frame: usize) -> f32 {
+        self.input[channel][frame]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nodes::new_node_engine, Cell, Context, Matrix, NodeExecutor, NodeId};
+
+    const NFRAMES: usize = 64;
+
+    /// Sets up a `Sin -> Out` patch, so that `Out` writes a non silent
+    /// signal to its first channel.
+    fn sin_out_engine() -> (Matrix, NodeExecutor) {
+        let (node_conf, mut node_exec) = new_node_engine();
+        let mut matrix = Matrix::new(node_conf, 8, 8);
+
+        matrix.place(3, 3, Cell::empty(NodeId::Sin(0)).out(Some(0), None, None));
+        matrix.place(4, 3, Cell::empty(NodeId::Out(0)).input(None, None, Some(0)));
+        matrix.sync().unwrap();
+
+        node_exec.set_sample_rate(44100.0);
+        node_exec.process_graph_updates();
+
+        (matrix, node_exec)
+    }
+
+    #[test]
+    fn check_interleaved_matches_per_channel_output() {
+        let input_l = [0.0; NFRAMES];
+        let input_r = [0.0; NFRAMES];
+        let input: &[&[f32]] = &[&input_l[..], &input_r[..]];
+
+        let mut l = [0.0; NFRAMES];
+        let mut r = [0.0; NFRAMES];
+        let (_matrix, mut node_exec) = sin_out_engine();
+        {
+            let mut output = [&mut l[..], &mut r[..]];
+            let mut ctx = Context { nframes: NFRAMES, output: &mut output[..], input };
+            node_exec.process(&mut ctx);
+        }
+
+        let mut interleaved = [0.0; NFRAMES * 2];
+        let (_matrix, mut node_exec) = sin_out_engine();
+        let mut ctx = InterleavedContext {
+            nframes: NFRAMES,
+            channels: 2,
+            output: &mut interleaved[..],
+            input,
+        };
+        node_exec.process(&mut ctx);
+
+        assert!(l.iter().any(|s| *s != 0.0), "Out must produce a signal");
+        for (frame, (sl, sr)) in interleaved.chunks(2).zip(l.iter().zip(r.iter())) {
+            assert_eq!(frame, &[*sl, *sr]);
+        }
+    }
+
+    /// Writes a fixed test pattern through the context:
+    /// channel 0 gets `frame + 1`, channel 1 gets `-(frame + 1)`.
+    fn render_pattern(channels: usize, nframes: usize) -> Vec<f32> {
+        let mut buf = vec![0.0; nframes * channels];
+        let input: &[&[f32]] = &[];
+        let mut ctx = InterleavedContext { nframes, channels, output: &mut buf[..], input };
+
+        for frame in 0..ctx.nframes() {
+            ctx.output(0, frame, frame as f32 + 1.0);
+            ctx.output(1, frame, -(frame as f32 + 1.0));
+        }
+
+        buf
+    }
+
+    #[test]
+    fn check_interleaved_stereo() {
+        assert_eq!(render_pattern(2, 3), vec![1.0, -1.0, 2.0, -2.0, 3.0, -3.0]);
+    }
+
+    #[test]
+    fn check_interleaved_mono_drops_extra_channels() {
+        assert_eq!(render_pattern(1, 3), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn check_interleaved_unwritten_channels_stay_silent() {
+        assert_eq!(render_pattern(4, 2), vec![1.0, -1.0, 0.0, 0.0, 2.0, -2.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn check_interleaved_input() {
+        let mut buf = [0.0; 4];
+        let in_l = [0.5, 0.25];
+        let in_r = [-0.5, -0.25];
+        let input: &[&[f32]] = &[&in_l[..], &in_r[..]];
+        let mut ctx = InterleavedContext { nframes: 2, channels: 2, output: &mut buf[..], input };
+
+        assert_eq!(ctx.input(0, 1), 0.25);
+        assert_eq!(ctx.input(1, 0), -0.5);
+    }
+}
@@ -12,6 +12,7 @@ use wlambda::vval::VVal;
 use wlambda::*;
 
 mod ext_param_model;
+mod interleaved_context;
 mod matrix_param_model;
 
 pub use ext_param_model::ExtParam;
@@ -24,6 +25,7 @@ use std::sync::{Arc, Mutex};
 
 //pub use uimsg_queue::Msg;
 pub use hexodsp::*;
+pub use interleaved_context::InterleavedContext;
 use hexotk::EditableText;
 use hexotk::MarkdownWichtextGenerator;
 //pub use hexotk::*;
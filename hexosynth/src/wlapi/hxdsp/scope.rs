@@ -11,10 +11,54 @@ use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use wlambda::*;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TriggerEdge {
+    Rising,
+    Falling,
+}
+
 struct ScopeData {
     matrix: Arc<Mutex<Matrix>>,
     handle: Arc<ScopeHandle>,
     node_id: NodeId,
+    /// The signal the trigger looks at, `None` means free running.
+    trig_sig: Option<usize>,
+    trig_thresh: f32,
+    trig_edge: TriggerEdge,
+    trig_offs: usize,
+}
+
+/// Searches the `(max, min)` pairs returned by `read` for the last trigger
+/// crossing in the first half of a buffer of `len` pairs, so that at least
+/// half of the buffer holds signal after the crossing.
+/// Returns the index right after the crossing, or 0 if none was found.
+fn find_trigger_offs(
+    len: usize,
+    thresh: f32,
+    edge: TriggerEdge,
+    read: impl Fn(usize) -> (f32, f32),
+) -> usize {
+    if len < 2 {
+        return 0;
+    }
+
+    let (mut prev_max, mut prev_min) = read(len / 2);
+    for i in (0..(len / 2)).rev() {
+        let (s_max, s_min) = read(i);
+
+        let crossed = match edge {
+            TriggerEdge::Rising => s_max < thresh && prev_max >= thresh,
+            TriggerEdge::Falling => s_min > thresh && prev_min <= thresh,
+        };
+        if crossed {
+            return i + 1;
+        }
+
+        prev_max = s_max;
+        prev_min = s_min;
+    }
+
+    0
 }
 
 impl ScopeData {
@@ -29,6 +73,81 @@ impl ScopeData {
             }
         };
         self.node_id = node_id;
+        self.trig_offs = 0;
+    }
+
+    pub fn set_trigger(&mut self, sig: usize, thresh: f32, edge: TriggerEdge) {
+        self.trig_sig = Some(sig);
+        self.trig_thresh = thresh;
+        self.trig_edge = edge;
+        self.trig_offs = 0;
+    }
+
+    pub fn set_free_run(&mut self) {
+        self.trig_sig = None;
+        self.trig_offs = 0;
+    }
+
+    /// Looks up the latest trigger crossing in the scope buffer.
+    /// Call this once per frame, so that all signals drawn in that
+    /// frame share the same offset.
+    pub fn update_trigger(&mut self) {
+        self.trig_offs = if let Some(trig_sig) = self.trig_sig {
+            find_trigger_offs(self.handle.len(), self.trig_thresh, self.trig_edge, |i| {
+                self.handle.read(trig_sig, i)
+            })
+        } else {
+            0
+        };
+    }
+
+    /// Minimum over the displayed samples, 0.0 if there are none.
+    pub fn signal_min(&self, sig: usize) -> f32 {
+        let len = self.signal_len();
+        if len == 0 {
+            return 0.0;
+        }
+
+        let mut min = f32::INFINITY;
+        for i in 0..len {
+            let (_s_max, s_min) = self.get(sig, i);
+            min = min.min(s_min);
+        }
+        min
+    }
+
+    /// Maximum over the displayed samples, 0.0 if there are none.
+    pub fn signal_max(&self, sig: usize) -> f32 {
+        let len = self.signal_len();
+        if len == 0 {
+            return 0.0;
+        }
+
+        let mut max = f32::NEG_INFINITY;
+        for i in 0..len {
+            let (s_max, _s_min) = self.get(sig, i);
+            max = max.max(s_max);
+        }
+        max
+    }
+
+    /// Root mean square of the min/max envelope of the displayed samples.
+    ///
+    /// This is not the RMS of the signal: each scope bucket only stores
+    /// the extremes of the samples it covers. Unless a bucket holds a
+    /// single sample, a full scale sine reads about 1.0 instead of 0.707.
+    pub fn signal_env_level(&self, sig: usize) -> f32 {
+        let len = self.signal_len();
+        if len == 0 {
+            return 0.0;
+        }
+
+        let mut sum = 0.0;
+        for i in 0..len {
+            let (s_max, s_min) = self.get(sig, i);
+            sum += (s_max * s_max + s_min * s_min) * 0.5;
+        }
+        (sum / (len as f32)).sqrt()
     }
 }
 
@@ -37,28 +156,33 @@ impl ScopeModel for ScopeData {
         3
     }
     fn signal_len(&self) -> usize {
-        self.handle.len()
+        // The trigger crossing is always in the first half of the buffer,
+        // so only show half of it to have real samples across the display:
+        if self.trig_sig.is_some() {
+            self.handle.len() / 2
+        } else {
+            self.handle.len()
+        }
     }
     fn get(&self, sig: usize, idx: usize) -> (f32, f32) {
-        self.handle.read(sig, idx)
+        self.handle.read(sig, idx + self.trig_offs)
     }
     fn get_offs_gain(&self, sig: usize) -> (f32, f32) {
         self.handle.get_offs_gain(sig)
     }
     fn get_threshold(&self) -> Option<f32> {
-        self.handle.get_threshold()
+        if self.trig_sig.is_some() {
+            Some(self.trig_thresh)
+        } else {
+            self.handle.get_threshold()
+        }
     }
     fn is_active(&self, sig: usize) -> bool {
         self.handle.is_active(sig)
     }
     fn fmt_val(&self, sig: usize, buf: &mut [u8]) -> usize {
-        let mut max = -99999.0_f32;
-        let mut min = 99999.0_f32;
-        for i in 0..self.signal_len() {
-            let (s_max, s_min) = self.handle.read(sig, i);
-            max = max.max(s_max);
-            min = min.min(s_min);
-        }
+        let max = self.signal_max(sig);
+        let min = self.signal_min(sig);
         let rng = max - min;
 
         use std::io::Write;
@@ -96,6 +220,10 @@ impl VScopeModel {
             matrix: matrix.clone(),
             handle,
             node_id: node_id.clone(),
+            trig_sig: None,
+            trig_thresh: 0.0,
+            trig_edge: TriggerEdge::Rising,
+            trig_offs: 0,
         })))
     }
 }
@@ -122,6 +250,66 @@ impl VValUserData for VScopeModel {
 
                 Ok(VVal::Bol(true))
             }
+            "set_trigger" => {
+                arg_chk!(
+                    args,
+                    3,
+                    "scope_model.set_trigger[sig_idx, threshold, :rising | :falling]"
+                );
+
+                let edge = args[2].with_s_ref(|s| match s {
+                    "rising" => Some(TriggerEdge::Rising),
+                    "falling" => Some(TriggerEdge::Falling),
+                    _ => None,
+                });
+                let edge = if let Some(edge) = edge {
+                    edge
+                } else {
+                    return Ok(VVal::err_msg(&format!(
+                        "Unknown trigger edge: {}, expected :rising or :falling",
+                        args[2].s_raw()
+                    )));
+                };
+
+                let sig = args[0].i();
+                if sig < 0 || sig as usize >= self.0.borrow().signal_count() {
+                    return Ok(VVal::err_msg(&format!("Bad signal index: {}", sig)));
+                }
+
+                self.0.borrow_mut().set_trigger(sig as usize, args[1].f() as f32, edge);
+
+                Ok(VVal::Bol(true))
+            }
+            "update_trigger" => {
+                arg_chk!(args, 0, "scope_model.update_trigger[]");
+                self.0.borrow_mut().update_trigger();
+
+                Ok(VVal::Bol(true))
+            }
+            "set_free_run" => {
+                arg_chk!(args, 0, "scope_model.set_free_run[]");
+                self.0.borrow_mut().set_free_run();
+
+                Ok(VVal::Bol(true))
+            }
+            "min" | "max" | "env_level" => {
+                arg_chk!(args, 1, "scope_model.min/max/env_level[sig_idx]");
+
+                let sig = args[0].i();
+                let data = self.0.borrow();
+                if sig < 0 || sig as usize >= data.signal_count() {
+                    return Ok(VVal::err_msg(&format!("Bad signal index: {}", sig)));
+                }
+
+                let sig = sig as usize;
+                let v = match key {
+                    "min" => data.signal_min(sig),
+                    "max" => data.signal_max(sig),
+                    _ => data.signal_env_level(sig),
+                };
+
+                Ok(VVal::Flt(v as f64))
+            }
             _ => Ok(VVal::err_msg(&format!("Unknown method called: {}", key))),
         }
     }
@@ -130,3 +318,32 @@ impl VValUserData for VScopeModel {
 pub fn vv2scope_model(mut v: VVal) -> Option<Rc<RefCell<dyn ScopeModel>>> {
     v.with_usr_ref(|model: &mut VScopeModel| model.0.clone() as Rc<RefCell<dyn ScopeModel>>)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(samples: &[f32]) -> Vec<(f32, f32)> {
+        samples.iter().map(|s| (*s, *s)).collect()
+    }
+
+    #[test]
+    fn check_trigger_rising() {
+        let buf = pairs(&[0.0, 0.6, 0.0, 0.6, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(find_trigger_offs(buf.len(), 0.5, TriggerEdge::Rising, |i| buf[i]), 3);
+    }
+
+    #[test]
+    fn check_trigger_falling() {
+        let buf = pairs(&[0.6, 0.0, 0.6, 0.6, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(find_trigger_offs(buf.len(), 0.5, TriggerEdge::Falling, |i| buf[i]), 4);
+    }
+
+    #[test]
+    fn check_trigger_no_crossing() {
+        let buf = pairs(&[0.0, 0.1, 0.2, 0.3, 0.4, 0.6, 0.7, 0.8]);
+        assert_eq!(find_trigger_offs(buf.len(), 0.5, TriggerEdge::Rising, |i| buf[i]), 0);
+        assert_eq!(find_trigger_offs(buf.len(), 0.5, TriggerEdge::Falling, |i| buf[i]), 0);
+        assert_eq!(find_trigger_offs(1, 0.5, TriggerEdge::Rising, |i| buf[i]), 0);
+    }
+}
@@ -1370,6 +1370,7 @@ root.add app_panel;
     # TODO: FIXME:
     unwrap ~ matrix.check_block_function 0;
     matrix.handle_graph_events[];
+    scope_handle.update_trigger[];
 
     iter r matrix_records {
         #d# std:displayln "REC:" r;
@@ -33,15 +33,13 @@ where {
     node_exec.set_sample_rate(sample_rate);
 
     let input_bufs = [[0.0; hexodsp::dsp::MAX_BLOCK_SIZE]; 2];
-    let mut outputbufs = [[0.0; hexodsp::dsp::MAX_BLOCK_SIZE]; 2];
 
     let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
     let stream = device.build_output_stream(
         config,
         move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
             let mut frames_left = data.len() / channels;
-
-            let mut out_iter = data.chunks_mut(channels);
+            let mut offs = 0;
 
             node_exec.process_graph_updates();
 
@@ -54,37 +52,15 @@ where {
 
                 let input = &[&input_bufs[0][0..cur_nframes], &input_bufs[1][0..cur_nframes]];
 
-                let split = outputbufs.split_at_mut(1);
-
-                let mut output =
-                    [&mut ((split.0[0])[0..cur_nframes]), &mut ((split.1[0])[0..cur_nframes])];
+                let output = &mut data[offs..(offs + cur_nframes * channels)];
+                output.fill(0.0);
 
-                let mut context = Context { nframes: cur_nframes, output: &mut output[..], input };
-
-                context.output[0].fill(0.0);
-                context.output[1].fill(0.0);
+                let mut context =
+                    InterleavedContext { nframes: cur_nframes, channels, output, input };
 
                 node_exec.process(&mut context);
 
-                // This copy loop is a bit inefficient, it's likely you can
-                // pass the right array slices directly into node_exec.process()
-                // via the Context structure. But I was too lazy at this point
-                // to figure this out. Check also the Jack example for a more
-                // efficient solution.
-                for i in 0..cur_nframes {
-                    if let Some(frame) = out_iter.next() {
-                        let mut ctx_chan = 0;
-                        for sample in frame.iter_mut() {
-                            *sample = context.output[ctx_chan][i];
-
-                            ctx_chan += 1;
-                            if ctx_chan > context.output.len() {
-                                ctx_chan = context.output.len() - 1;
-                            }
-                        }
-                    }
-                }
-
+                offs += cur_nframes * channels;
                 frames_left -= cur_nframes;
             }
         },